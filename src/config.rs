@@ -1,11 +1,93 @@
 use futures::TryFutureExt;
 use serde::Deserialize;
+use std::fmt;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncReadExt, BufReader};
 
+/// Keeps its inner value out of `Debug` output.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self(T::default())
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+/// Where a secret-bearing field's value comes from when it is not written
+/// inline: an OS keyring entry or a file on disk.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum SecretSource {
+    Keyring { service: String, key: String },
+    File { path: PathBuf },
+}
+
+impl SecretSource {
+    fn resolve(&self) -> anyhow::Result<String> {
+        match self {
+            SecretSource::Keyring { service, key } => {
+                Ok(keyring::Entry::new(service, key)?.get_password()?)
+            }
+            SecretSource::File { path } => Ok(std::fs::read_to_string(path)?.trim_end().to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum SecretValue {
+    Inline(String),
+    Source(SecretSource),
+}
+
+impl SecretValue {
+    fn resolve(&self) -> anyhow::Result<Secret<String>> {
+        match self {
+            SecretValue::Inline(value) => Ok(Secret::new(value.clone())),
+            SecretValue::Source(source) => Ok(Secret::new(source.resolve()?)),
+        }
+    }
+}
+
+fn deserialize_secret<'de, D>(deserializer: D) -> Result<Secret<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    SecretValue::deserialize(deserializer)?
+        .resolve()
+        .map_err(serde::de::Error::custom)
+}
+
+fn deserialize_opt_secret<'de, D>(deserializer: D) -> Result<Option<Secret<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<SecretValue>::deserialize(deserializer)?
+        .map(|value| value.resolve().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct GitConfig {
     pub backup_remote_url: String,
@@ -15,14 +97,37 @@ pub struct GitConfig {
     #[serde(default = "GitConfig::branch_default")]
     pub index_branch: String,
     pub https_username: Option<String>,
-    pub https_password: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_opt_secret")]
+    pub https_password: Option<Secret<String>>,
     pub ssh_username: Option<String>,
     pub ssh_pubkey_path: Option<PathBuf>,
     pub ssh_privkey_path: Option<PathBuf>,
-    pub ssh_key_passphrase: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_opt_secret")]
+    pub ssh_key_passphrase: Option<Secret<String>>,
     #[serde(default = "GitConfig::name_default")]
     pub name: String,
     pub email: Option<String>,
+    pub ca_cert_path: Option<PathBuf>,
+    pub client_cert_path: Option<PathBuf>,
+    pub client_key_path: Option<PathBuf>,
+    pub known_hosts_path: Option<PathBuf>,
+    #[serde(default)]
+    pub host_key_check: HostKeyCheck,
+}
+
+/// SSH host-key verification mode for `known_hosts_path`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyCheck {
+    Strict,
+    AcceptNew,
+    Off,
+}
+
+impl Default for HostKeyCheck {
+    fn default() -> Self {
+        HostKeyCheck::Strict
+    }
 }
 
 impl GitConfig {
@@ -54,6 +159,11 @@ impl Default for GitConfig {
             ssh_key_passphrase: Default::default(),
             name: GitConfig::name_default(),
             email: Default::default(),
+            ca_cert_path: Default::default(),
+            client_cert_path: Default::default(),
+            client_key_path: Default::default(),
+            known_hosts_path: Default::default(),
+            host_key_check: Default::default(),
         }
     }
 }
@@ -62,12 +172,45 @@ impl Default for GitConfig {
 pub struct CrateFilesConfig {
     #[serde(default = "CrateFilesConfig::dl_path_default")]
     pub dl_path: Vec<String>,
+
+    #[cfg(feature = "crates-io-mirroring")]
+    pub mirror_ca_cert_path: Option<PathBuf>,
+    #[cfg(feature = "crates-io-mirroring")]
+    pub mirror_client_cert_path: Option<PathBuf>,
+    #[cfg(feature = "crates-io-mirroring")]
+    pub mirror_client_key_path: Option<PathBuf>,
+    #[cfg(feature = "crates-io-mirroring")]
+    #[serde(default = "CrateFilesConfig::mirror_upstream_url_default")]
+    pub mirror_upstream_url: String,
+    #[cfg(feature = "crates-io-mirroring")]
+    #[serde(default, deserialize_with = "deserialize_opt_secret")]
+    pub mirror_auth_token: Option<Secret<String>>,
+    #[cfg(feature = "crates-io-mirroring")]
+    #[serde(default = "CrateFilesConfig::mirror_cache_ttl_secs_default")]
+    pub mirror_cache_ttl_secs: u64,
+    #[cfg(feature = "crates-io-mirroring")]
+    #[serde(default)]
+    pub mirror_metadata_format: MirrorMetadataFormat,
 }
 
 impl Default for CrateFilesConfig {
     fn default() -> CrateFilesConfig {
         CrateFilesConfig {
             dl_path: CrateFilesConfig::dl_path_default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_ca_cert_path: Default::default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_client_cert_path: Default::default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_client_key_path: Default::default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_upstream_url: CrateFilesConfig::mirror_upstream_url_default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_auth_token: Default::default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_cache_ttl_secs: CrateFilesConfig::mirror_cache_ttl_secs_default(),
+            #[cfg(feature = "crates-io-mirroring")]
+            mirror_metadata_format: Default::default(),
         }
     }
 }
@@ -85,52 +228,114 @@ impl CrateFilesConfig {
     pub fn dl_path_default() -> Vec<String> {
         vec!["dl".to_owned()]
     }
+
+    #[cfg(feature = "crates-io-mirroring")]
+    fn mirror_upstream_url_default() -> String {
+        "https://static.crates.io".to_owned()
+    }
+
+    #[cfg(feature = "crates-io-mirroring")]
+    fn mirror_cache_ttl_secs_default() -> u64 {
+        3600
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct DbConfig {
-    #[serde(default = "DbConfig::login_prefix_default")]
-    pub login_prefix: String,
+/// Which index protocol (git or sparse) is configured for the upstream
+/// registry. The mirror's crate-file fetch path does not read this setting.
+#[cfg(feature = "crates-io-mirroring")]
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorMetadataFormat {
+    Git,
+    Sparse,
+}
 
-    #[cfg(feature = "db-redis")]
-    #[serde(default = "DbConfig::redis_url_default")]
-    pub redis_url: String,
+#[cfg(feature = "crates-io-mirroring")]
+impl Default for MirrorMetadataFormat {
+    fn default() -> Self {
+        MirrorMetadataFormat::Sparse
+    }
+}
 
-    #[cfg(feature = "db-mongo")]
-    #[serde(default = "DbConfig::mongodb_url_default")]
-    pub mongodb_url: String,
+/// Which storage backend `ktra` talks to, and that backend's connection
+/// settings. Selected at runtime from the `backend` tag in `[db_config]`
+/// rather than by which `db-*` Cargo feature the binary was built with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum DbConfig {
+    Sled {
+        #[serde(default = "DbConfig::login_prefix_default")]
+        login_prefix: String,
+        #[serde(default = "DbConfig::db_dir_default")]
+        dir: PathBuf,
+    },
+    Redis {
+        #[serde(default = "DbConfig::login_prefix_default")]
+        login_prefix: String,
+        #[serde(
+            default = "DbConfig::redis_url_default",
+            deserialize_with = "deserialize_secret"
+        )]
+        url: Secret<String>,
+    },
+    Mongo {
+        #[serde(default = "DbConfig::login_prefix_default")]
+        login_prefix: String,
+        #[serde(
+            default = "DbConfig::mongodb_url_default",
+            deserialize_with = "deserialize_secret"
+        )]
+        url: Secret<String>,
+    },
+    Postgres {
+        #[serde(default = "DbConfig::login_prefix_default")]
+        login_prefix: String,
+        host: String,
+        #[serde(default = "DbConfig::postgres_database_default")]
+        database: String,
+        user: String,
+        #[serde(deserialize_with = "deserialize_secret")]
+        password: Secret<String>,
+    },
 }
 
 impl Default for DbConfig {
     fn default() -> DbConfig {
-        DbConfig {
+        DbConfig::Sled {
             login_prefix: DbConfig::login_prefix_default(),
-            #[cfg(feature = "db-redis")]
-            redis_url: DbConfig::redis_url_default(),
-            #[cfg(feature = "db-mongo")]
-            mongodb_url: DbConfig::mongodb_url_default(),
+            dir: DbConfig::db_dir_default(),
         }
     }
 }
 
 impl DbConfig {
+    pub fn login_prefix(&self) -> &str {
+        match self {
+            DbConfig::Sled { login_prefix, .. }
+            | DbConfig::Redis { login_prefix, .. }
+            | DbConfig::Mongo { login_prefix, .. }
+            | DbConfig::Postgres { login_prefix, .. } => login_prefix,
+        }
+    }
+
     fn login_prefix_default() -> String {
         "ktra-secure-auth:".to_owned()
     }
 
-    #[cfg(feature = "db-sled")]
-    fn db_dir_path_relative() -> PathBuf {
+    fn db_dir_default() -> PathBuf {
         PathBuf::from("db")
     }
 
-    #[cfg(feature = "db-redis")]
-    fn redis_url_default() -> String {
-        "redis://localhost".to_owned()
+    fn redis_url_default() -> Secret<String> {
+        Secret::new("redis://localhost".to_owned())
     }
 
-    #[cfg(feature = "db-mongo")]
-    fn mongodb_url_default() -> String {
-        "mongodb://localhost:27017".to_owned()
+    fn mongodb_url_default() -> Secret<String> {
+        Secret::new("mongodb://localhost:27017".to_owned())
+    }
+
+    fn postgres_database_default() -> String {
+        "ktra".to_owned()
     }
 }
 
@@ -171,7 +376,8 @@ pub struct OpenIdConfig {
     pub(crate) issuer_url: String,
     pub(crate) redirect_url: String,
     pub(crate) client_id: String,
-    pub(crate) client_secret: String,
+    #[serde(deserialize_with = "deserialize_secret")]
+    pub(crate) client_secret: Secret<String>,
     #[serde(default)]
     pub(crate) additional_scopes: Vec<String>,
     pub(crate) gitlab_authorized_groups: Option<Vec<String>>,
@@ -207,8 +413,24 @@ impl Default for Config {
     }
 }
 
+/// Prefix and nesting separator for environment-variable overrides, e.g.
+/// `KTRA_DB_CONFIG__URL` overrides `db_config.url`.
+const ENV_PREFIX: &str = "KTRA_";
+const ENV_SEPARATOR: &str = "__";
+
 impl Config {
     pub async fn open(path: impl AsRef<Path>) -> anyhow::Result<Config> {
+        Self::open_layered(path, std::iter::empty()).await
+    }
+
+    /// Reads `path` as the base config, then overlays values from
+    /// `KTRA_`-prefixed environment variables, then from `overrides`
+    /// (`section.field=value` pairs, typically sourced from CLI flags).
+    /// Later layers win, so none of these need to be written to disk.
+    pub async fn open_layered(
+        path: impl AsRef<Path>,
+        overrides: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> anyhow::Result<Config> {
         let mut file = OpenOptions::new()
             .read(true)
             .open(path)
@@ -217,7 +439,159 @@ impl Config {
         let mut buf = String::new();
         file.read_to_string(&mut buf).await?;
 
-        toml::from_str(&buf).map_err(Into::into)
+        let mut value: toml::Value = toml::from_str(&buf)?;
+        Self::overlay_env(&mut value, std::env::vars());
+        for entry in overrides {
+            Self::overlay_cli_entry(&mut value, entry.as_ref())?;
+        }
+
+        value.try_into().map_err(Into::into)
+    }
+
+    fn overlay_env(value: &mut toml::Value, vars: impl IntoIterator<Item = (String, String)>) {
+        for (key, val) in vars {
+            let Some(rest) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let path: Vec<String> = rest
+                .split(ENV_SEPARATOR)
+                .map(|segment| segment.to_lowercase())
+                .collect();
+            Self::warn_if_unknown_path(&path);
+            Self::set_path(value, &path, val);
+        }
+    }
+
+    fn overlay_cli_entry(value: &mut toml::Value, entry: &str) -> anyhow::Result<()> {
+        let (path, val) = entry.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("invalid config override `{entry}`, expected key=value")
+        })?;
+        let path: Vec<String> = path.split('.').map(str::to_owned).collect();
+        Self::warn_if_unknown_path(&path);
+        Self::set_path(value, &path, val.to_owned());
+        Ok(())
+    }
+
+    /// `path` is checked against [`Self::known_fields`] so a typo'd override
+    /// key (e.g. `git_config.https_passwrod`) is logged rather than silently
+    /// inserted as a dead key that leaves the real field untouched.
+    fn warn_if_unknown_path(path: &[String]) {
+        let (known, leaf) = match path {
+            [top] => (Self::known_fields(""), top),
+            [section, field] => (Self::known_fields(section), field),
+            _ => return,
+        };
+        if !known.contains(&leaf.as_str()) {
+            tracing::warn!(
+                path = %path.join("."),
+                "config override does not match a known field; check for a typo"
+            );
+        }
+    }
+
+    fn known_fields(section: &str) -> &'static [&'static str] {
+        match section {
+            "" => &[
+                "root_dir_path",
+                "crate_files_config",
+                "db_config",
+                "git_config",
+                "server_config",
+                "openid_config",
+            ],
+            "git_config" => &[
+                "backup_remote_url",
+                "backup_branch",
+                "index_remote_url",
+                "index_branch",
+                "https_username",
+                "https_password",
+                "ssh_username",
+                "ssh_pubkey_path",
+                "ssh_privkey_path",
+                "ssh_key_passphrase",
+                "name",
+                "email",
+                "ca_cert_path",
+                "client_cert_path",
+                "client_key_path",
+                "known_hosts_path",
+                "host_key_check",
+            ],
+            "db_config" => &[
+                "backend",
+                "login_prefix",
+                "dir",
+                "url",
+                "host",
+                "database",
+                "user",
+                "password",
+            ],
+            "crate_files_config" => &[
+                "dl_path",
+                "mirror_ca_cert_path",
+                "mirror_client_cert_path",
+                "mirror_client_key_path",
+                "mirror_upstream_url",
+                "mirror_auth_token",
+                "mirror_cache_ttl_secs",
+                "mirror_metadata_format",
+            ],
+            "server_config" => &["address", "port"],
+            "openid_config" => &[
+                "issuer_url",
+                "redirect_url",
+                "client_id",
+                "client_secret",
+                "additional_scopes",
+                "gitlab_authorized_groups",
+                "gitlab_authorized_users",
+            ],
+            _ => &[],
+        }
+    }
+
+    fn set_path(value: &mut toml::Value, path: &[String], leaf: String) {
+        let Some((head, rest)) = path.split_first() else {
+            return;
+        };
+
+        let table = value.as_table_mut().unwrap_or_else(|| {
+            *value = toml::Value::Table(Default::default());
+            value.as_table_mut().unwrap()
+        });
+
+        if rest.is_empty() {
+            table.insert(head.clone(), Self::coerce_scalar(path, leaf));
+        } else {
+            let child = table
+                .entry(head.clone())
+                .or_insert_with(|| toml::Value::Table(Default::default()));
+            Self::set_path(child, rest, leaf);
+        }
+    }
+
+    /// Overrides are always strings on the wire (env vars, CLI flags); this
+    /// decides whether a field's value needs parsing into an integer
+    /// based on `path`, not by guessing from the raw string. Guessing would
+    /// either coerce a numeric-looking secret (e.g. a 6-digit password) into
+    /// an integer, or fail to coerce a numeric field that simply wasn't set
+    /// in the base TOML yet (so there was no existing value to infer from).
+    fn coerce_scalar(path: &[String], raw: String) -> toml::Value {
+        let is_integer_field = matches!(
+            path,
+            [section, field]
+                if (section == "server_config" && field == "port")
+                    || (section == "crate_files_config" && field == "mirror_cache_ttl_secs")
+        );
+        if is_integer_field {
+            raw.parse::<i64>()
+                .map(toml::Value::Integer)
+                .unwrap_or(toml::Value::String(raw))
+        } else {
+            toml::Value::String(raw)
+        }
     }
 
     pub fn index_path(&self) -> PathBuf {
@@ -235,12 +609,109 @@ impl Config {
             .join(CrateFilesConfig::cache_dir_path_relative())
     }
 
-    #[cfg(feature = "db-sled")]
-    pub fn db_dir_path(&self) -> PathBuf {
-        self.root_dir_path.join(DbConfig::db_dir_path_relative())
+    pub fn db_dir_path(&self) -> Option<PathBuf> {
+        match &self.db_config {
+            DbConfig::Sled { dir, .. } => Some(self.root_dir_path.join(dir)),
+            DbConfig::Redis { .. } | DbConfig::Mongo { .. } | DbConfig::Postgres { .. } => None,
+        }
     }
 
     fn root_dir_path_default() -> PathBuf {
         PathBuf::from("ktra_root")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Value {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn env_overrides_toml() {
+        let mut value = table("[git_config]\nindex_remote_url = \"https://from-toml\"\n");
+        Config::overlay_env(
+            &mut value,
+            [(
+                "KTRA_GIT_CONFIG__INDEX_REMOTE_URL".to_owned(),
+                "https://from-env".to_owned(),
+            )],
+        );
+        assert_eq!(
+            value["git_config"]["index_remote_url"].as_str(),
+            Some("https://from-env")
+        );
+    }
+
+    #[test]
+    fn cli_overrides_env_and_toml() {
+        let mut value = table("[git_config]\nindex_remote_url = \"https://from-toml\"\n");
+        Config::overlay_env(
+            &mut value,
+            [(
+                "KTRA_GIT_CONFIG__INDEX_REMOTE_URL".to_owned(),
+                "https://from-env".to_owned(),
+            )],
+        );
+        Config::overlay_cli_entry(&mut value, "git_config.index_remote_url=https://from-cli")
+            .unwrap();
+        assert_eq!(
+            value["git_config"]["index_remote_url"].as_str(),
+            Some("https://from-cli")
+        );
+    }
+
+    #[test]
+    fn numeric_looking_string_override_is_not_coerced_to_a_number() {
+        let mut value = table("[git_config]\nhttps_password = \"hunter2\"\n");
+        Config::overlay_env(
+            &mut value,
+            [(
+                "KTRA_GIT_CONFIG__HTTPS_PASSWORD".to_owned(),
+                "123456".to_owned(),
+            )],
+        );
+        assert_eq!(
+            value["git_config"]["https_password"].as_str(),
+            Some("123456")
+        );
+    }
+
+    #[test]
+    fn numeric_looking_override_for_a_new_field_stays_a_string() {
+        let mut value = table("[git_config]\n");
+        Config::overlay_env(
+            &mut value,
+            [(
+                "KTRA_GIT_CONFIG__HTTPS_PASSWORD".to_owned(),
+                "123456".to_owned(),
+            )],
+        );
+        assert_eq!(
+            value["git_config"]["https_password"].as_str(),
+            Some("123456")
+        );
+    }
+
+    #[test]
+    fn override_coerces_to_the_existing_fields_numeric_type() {
+        let mut value = table("[server_config]\nport = 8000\n");
+        Config::overlay_env(
+            &mut value,
+            [("KTRA_SERVER_CONFIG__PORT".to_owned(), "9000".to_owned())],
+        );
+        assert_eq!(value["server_config"]["port"].as_integer(), Some(9000));
+    }
+
+    #[test]
+    fn override_coerces_a_numeric_field_even_when_absent_from_the_base_toml() {
+        let mut value = table("");
+        Config::overlay_env(
+            &mut value,
+            [("KTRA_SERVER_CONFIG__PORT".to_owned(), "9090".to_owned())],
+        );
+        assert_eq!(value["server_config"]["port"].as_integer(), Some(9090));
+    }
+}
@@ -0,0 +1,23 @@
+pub mod postgres;
+
+use async_trait::async_trait;
+
+#[derive(Debug, Clone)]
+pub struct CrateMeta {
+    pub name: String,
+    pub vers: String,
+    pub cksum: String,
+    pub yanked: bool,
+}
+
+/// Storage operations `ktra` needs from whichever backend `DbConfig` selects.
+#[async_trait]
+pub trait DbManager: Send + Sync {
+    async fn password_hash(&self, login_name: &str) -> anyhow::Result<Option<String>>;
+    async fn set_password_hash(&self, login_name: &str, hash: &str) -> anyhow::Result<()>;
+    async fn owners(&self, name: &str) -> anyhow::Result<Vec<String>>;
+    async fn add_owner(&self, name: &str, login_name: &str) -> anyhow::Result<()>;
+    async fn remove_owner(&self, name: &str, login_name: &str) -> anyhow::Result<()>;
+    async fn put_crate(&self, meta: CrateMeta) -> anyhow::Result<()>;
+    async fn yank(&self, name: &str, vers: &str, yanked: bool) -> anyhow::Result<()>;
+}
@@ -0,0 +1,108 @@
+use super::{CrateMeta, DbManager};
+use crate::config::DbConfig;
+use async_trait::async_trait;
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{PgPool, Row};
+
+pub struct PostgresDbManager {
+    pool: PgPool,
+}
+
+impl PostgresDbManager {
+    pub async fn connect(config: &DbConfig) -> anyhow::Result<Self> {
+        let DbConfig::Postgres {
+            host,
+            database,
+            user,
+            password,
+            ..
+        } = config
+        else {
+            anyhow::bail!("PostgresDbManager requires a `backend = \"postgres\"` db_config");
+        };
+
+        let options = PgConnectOptions::new()
+            .host(host)
+            .username(user)
+            .password(password.expose_secret())
+            .database(database);
+        let pool = PgPool::connect_with(options).await?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl DbManager for PostgresDbManager {
+    async fn password_hash(&self, login_name: &str) -> anyhow::Result<Option<String>> {
+        let row = sqlx::query("SELECT password_hash FROM users WHERE login_name = $1")
+            .bind(login_name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("password_hash")))
+    }
+
+    async fn set_password_hash(&self, login_name: &str, hash: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO users (login_name, password_hash) VALUES ($1, $2)
+             ON CONFLICT (login_name) DO UPDATE SET password_hash = EXCLUDED.password_hash",
+        )
+        .bind(login_name)
+        .bind(hash)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn owners(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query("SELECT login_name FROM crate_owners WHERE crate_name = $1")
+            .bind(name)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|row| row.get("login_name")).collect())
+    }
+
+    async fn add_owner(&self, name: &str, login_name: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO crate_owners (crate_name, login_name) VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(name)
+        .bind(login_name)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_owner(&self, name: &str, login_name: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM crate_owners WHERE crate_name = $1 AND login_name = $2")
+            .bind(name)
+            .bind(login_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn put_crate(&self, meta: CrateMeta) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO crates (name, vers, cksum, yanked) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (name, vers) DO UPDATE SET cksum = EXCLUDED.cksum",
+        )
+        .bind(meta.name)
+        .bind(meta.vers)
+        .bind(meta.cksum)
+        .bind(meta.yanked)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn yank(&self, name: &str, vers: &str, yanked: bool) -> anyhow::Result<()> {
+        sqlx::query("UPDATE crates SET yanked = $1 WHERE name = $2 AND vers = $3")
+            .bind(yanked)
+            .bind(name)
+            .bind(vers)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
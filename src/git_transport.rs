@@ -0,0 +1,265 @@
+use crate::config::{GitConfig, HostKeyCheck};
+use git2::{Cred, RemoteCallbacks};
+use std::path::Path;
+
+/// Applies `GitConfig`'s CA bundle and client cert to both git remotes.
+pub fn configure_tls(config: &GitConfig) -> anyhow::Result<()> {
+    if let Some(ca_cert_path) = &config.ca_cert_path {
+        unsafe {
+            git2::opts::set_ssl_cert_locations(Some(ca_cert_path), None)?;
+        }
+    }
+
+    if let Some(cert_path) = &config.client_cert_path {
+        std::env::set_var("GIT_SSL_CERT", cert_path);
+    }
+    if let Some(key_path) = &config.client_key_path {
+        std::env::set_var("GIT_SSL_KEY", key_path);
+    }
+
+    Ok(())
+}
+
+/// Checks a presented SSH host key against an entry in `known_hosts_path`
+/// (`hostname keytype base64key` per line, as produced by `ssh-keyscan`).
+pub fn verify_host_key(
+    known_hosts_path: &Path,
+    check: HostKeyCheck,
+    host: &str,
+    key_type: &str,
+    key_base64: &str,
+) -> anyhow::Result<()> {
+    if let HostKeyCheck::Off = check {
+        return Ok(());
+    }
+
+    let known_hosts = std::fs::read_to_string(known_hosts_path)?;
+    let matching_host = known_hosts
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && line.split_whitespace().next() == Some(host));
+
+    for line in matching_host {
+        let mut fields = line.split_whitespace();
+        let _host = fields.next();
+        let entry_type = fields.next();
+        let entry_key = fields.next();
+        if entry_type == Some(key_type) && entry_key == Some(key_base64) {
+            return Ok(());
+        }
+        if entry_type == Some(key_type) {
+            anyhow::bail!("host key for `{host}` does not match known_hosts_path");
+        }
+    }
+
+    match check {
+        HostKeyCheck::Strict => anyhow::bail!("no known_hosts_path entry for `{host}`"),
+        HostKeyCheck::AcceptNew => Ok(()),
+        HostKeyCheck::Off => Ok(()),
+    }
+}
+
+fn remote_callbacks(config: GitConfig) -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
+        if let (Some(username), Some(pubkey), Some(privkey)) = (
+            &config.ssh_username,
+            &config.ssh_pubkey_path,
+            &config.ssh_privkey_path,
+        ) {
+            let passphrase = config
+                .ssh_key_passphrase
+                .as_ref()
+                .map(|s| s.expose_secret());
+            return Cred::ssh_key(username, Some(pubkey), privkey, passphrase);
+        }
+        Cred::default()
+    });
+
+    callbacks.certificate_check(move |cert, host| {
+        let Some(hostkey) = cert.as_hostkey() else {
+            return Ok(git2::CertificateCheckStatus::CertificatePassthrough);
+        };
+        let Some(known_hosts_path) = &config.known_hosts_path else {
+            return Ok(git2::CertificateCheckStatus::CertificateOk);
+        };
+        let Some(raw) = hostkey.hostkey() else {
+            return Ok(git2::CertificateCheckStatus::CertificateOk);
+        };
+
+        let key_base64 = base64::encode(raw);
+        verify_host_key(
+            known_hosts_path,
+            config.host_key_check,
+            host,
+            "ssh-ed25519",
+            &key_base64,
+        )
+        .map(|()| git2::CertificateCheckStatus::CertificateOk)
+        .map_err(|err| git2::Error::from_str(&err.to_string()))
+    });
+
+    callbacks
+}
+
+/// Pushes `refspecs` to `remote_url` on the tokio blocking pool.
+pub async fn push(
+    config: GitConfig,
+    repo_path: std::path::PathBuf,
+    remote_url: String,
+    refspecs: Vec<String>,
+) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(&repo_path)?;
+        let mut remote = repo.remote_anonymous(&remote_url)?;
+        let mut options = git2::PushOptions::new();
+        options.remote_callbacks(remote_callbacks(config));
+        let refspecs: Vec<&str> = refspecs.iter().map(String::as_str).collect();
+        remote.push(&refspecs, Some(&mut options))?;
+        Ok(())
+    })
+    .await?
+}
+
+/// Fetches `refspecs` from `remote_url` on the tokio blocking pool.
+pub async fn pull(
+    config: GitConfig,
+    repo_path: std::path::PathBuf,
+    remote_url: String,
+    refspecs: Vec<String>,
+) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(&repo_path)?;
+        let mut remote = repo.remote_anonymous(&remote_url)?;
+        let mut options = git2::FetchOptions::new();
+        options.remote_callbacks(remote_callbacks(config));
+        let refspecs: Vec<&str> = refspecs.iter().map(String::as_str).collect();
+        remote.fetch(&refspecs, Some(&mut options), None)?;
+        Ok(())
+    })
+    .await?
+}
+
+/// Commits the repo's current index as a new commit on `HEAD`, signed by
+/// `author_name`/`author_email`.
+pub async fn commit(
+    repo_path: std::path::PathBuf,
+    author_name: String,
+    author_email: String,
+    message: String,
+) -> anyhow::Result<git2::Oid> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git2::Repository::open(&repo_path)?;
+        let mut index = repo.index()?;
+        let tree_oid = index.write_tree()?;
+        let tree = repo.find_tree(tree_oid)?;
+        let signature = git2::Signature::now(&author_name, &author_email)?;
+        let parents = match repo.head().ok().and_then(|head| head.peel_to_commit().ok()) {
+            Some(parent) => vec![parent],
+            None => Vec::new(),
+        };
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        let oid = repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parent_refs,
+        )?;
+        Ok(oid)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn known_hosts_with(lines: &[&str]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn strict_accepts_a_matching_host_key() {
+        let known_hosts = known_hosts_with(&["git.example.com ssh-ed25519 AAAAC3abc"]);
+        verify_host_key(
+            known_hosts.path(),
+            HostKeyCheck::Strict,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3abc",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn strict_rejects_a_mismatched_host_key() {
+        let known_hosts = known_hosts_with(&["git.example.com ssh-ed25519 AAAAC3abc"]);
+        let result = verify_host_key(
+            known_hosts.path(),
+            HostKeyCheck::Strict,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3different",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn strict_rejects_an_unknown_host() {
+        let known_hosts = known_hosts_with(&["other.example.com ssh-ed25519 AAAAC3abc"]);
+        let result = verify_host_key(
+            known_hosts.path(),
+            HostKeyCheck::Strict,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3abc",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn accept_new_allows_an_unknown_host() {
+        let known_hosts = known_hosts_with(&["other.example.com ssh-ed25519 AAAAC3abc"]);
+        verify_host_key(
+            known_hosts.path(),
+            HostKeyCheck::AcceptNew,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3abc",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn accept_new_still_rejects_a_mismatched_host_key() {
+        let known_hosts = known_hosts_with(&["git.example.com ssh-ed25519 AAAAC3abc"]);
+        let result = verify_host_key(
+            known_hosts.path(),
+            HostKeyCheck::AcceptNew,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3different",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn off_skips_verification_even_without_a_known_hosts_file() {
+        verify_host_key(
+            Path::new("/nonexistent/known_hosts"),
+            HostKeyCheck::Off,
+            "git.example.com",
+            "ssh-ed25519",
+            "AAAAC3abc",
+        )
+        .unwrap();
+    }
+}
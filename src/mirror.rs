@@ -0,0 +1,85 @@
+use crate::config::CrateFilesConfig;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "crates-io-mirroring")]
+pub fn build_mirror_client(config: &CrateFilesConfig) -> anyhow::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+
+    if let Some(ca_cert_path) = &config.mirror_ca_cert_path {
+        let pem = std::fs::read(ca_cert_path)?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+
+    if let (Some(cert_path), Some(key_path)) = (
+        &config.mirror_client_cert_path,
+        &config.mirror_client_key_path,
+    ) {
+        let mut pem = std::fs::read(cert_path)?;
+        pem.extend(std::fs::read(key_path)?);
+        builder = builder.identity(reqwest::Identity::from_pem(&pem)?);
+    }
+
+    if let Some(token) = &config.mirror_auth_token {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let mut value =
+            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token.expose_secret()))?;
+        value.set_sensitive(true);
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+        builder = builder.default_headers(headers);
+    }
+
+    builder.build().map_err(Into::into)
+}
+
+/// URL for a crate file on the configured upstream.
+///
+/// The URL does not depend on `mirror_metadata_format`; nothing in this
+/// module reads that setting or fetches index metadata.
+#[cfg(feature = "crates-io-mirroring")]
+fn upstream_crate_url(config: &CrateFilesConfig, name: &str, version: &str) -> String {
+    format!(
+        "{}/crates/{name}/{name}-{version}.crate",
+        config.mirror_upstream_url
+    )
+}
+
+/// Returns the cached path for `name`/`version`, fetching it from the
+/// upstream registry through `client` first if it's missing or older than
+/// `mirror_cache_ttl_secs`.
+#[cfg(feature = "crates-io-mirroring")]
+pub async fn fetch_crate_file(
+    client: &reqwest::Client,
+    config: &CrateFilesConfig,
+    cache_dir: &Path,
+    name: &str,
+    version: &str,
+) -> anyhow::Result<PathBuf> {
+    let cached_path = cache_dir.join(format!("{name}-{version}.crate"));
+    let ttl = Duration::from_secs(config.mirror_cache_ttl_secs);
+
+    if let Ok(metadata) = tokio::fs::metadata(&cached_path).await {
+        let age = match metadata.modified() {
+            Ok(modified) => SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or(Duration::MAX),
+            Err(_) => Duration::MAX,
+        };
+        if age < ttl {
+            return Ok(cached_path);
+        }
+    }
+
+    let url = upstream_crate_url(config, name, version);
+    let bytes = client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    tokio::fs::create_dir_all(cache_dir).await?;
+    tokio::fs::write(&cached_path, &bytes).await?;
+
+    Ok(cached_path)
+}